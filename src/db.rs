@@ -0,0 +1,14 @@
+use diesel::pg::PgConnection;
+use diesel::r2d2::{ConnectionManager, Pool};
+
+pub type DbPool = Pool<ConnectionManager<PgConnection>>;
+
+/// Builds the r2d2-backed connection pool shared across handlers via
+/// `web::Data`, reading the connection string from `DATABASE_URL`.
+pub fn establish_pool() -> DbPool {
+    let database_url = dotenv::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let manager = ConnectionManager::<PgConnection>::new(database_url);
+    Pool::builder()
+        .build(manager)
+        .expect("Failed to create database connection pool")
+}
@@ -1,23 +1,67 @@
+#[macro_use]
+extern crate diesel;
+
+mod db;
+mod models;
+mod schema;
+mod signature;
+mod slack;
+mod stream;
+mod worker;
+
 use actix_cors::Cors;
 use actix_web::{get, post};
-use actix_web::{http, web, App, HttpServer, ResponseError};
+use actix_web::{http, web, App, HttpRequest, HttpServer, ResponseError};
+use db::DbPool;
+use diesel::prelude::*;
 use dotenv::dotenv;
+use models::NewMessage;
 use reqwest;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use signature::verify_slack_signature;
+use slack::{
+    ConversationInfoResponse, ConversationsListResponse, PaginationQuery, PostMessageRequest,
+    PostMessageResponse, UsersListResponse,
+};
 use std::fmt::Display;
+use tokio::sync::mpsc::Sender;
+use worker::{InboundMessage, LlmConfig};
 
 #[derive(Deserialize, Serialize)]
 struct FormData {
     channel: String,
     message: String,
 }
+
+/// Config shared across handlers, kept separate from the `reqwest::Client`
+/// so each piece of `web::Data` can be cloned/extracted independently.
+struct SlackConfig {
+    signing_secret: String,
+}
+
+/// Body of a Slack Events API callback, covering the two shapes we care
+/// about: the one-time URL verification handshake and actual event
+/// callbacks. See https://api.slack.com/events/url_verification
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SlackEventPayload {
+    UrlVerification { challenge: String },
+    EventCallback { event: Value },
+}
+
 #[derive(Debug)]
 enum CustomError {
     SlackResponseError,
     BodyExtractionError,
     ConversionError,
+    InvalidSignature,
+    DatabaseError,
+    SlackApiError {
+        code: String,
+        needed: Option<String>,
+        provided: Option<String>,
+    },
 }
 
 impl Display for CustomError {
@@ -28,92 +72,260 @@ impl Display for CustomError {
             }
             CustomError::BodyExtractionError => write!(f, "Unable to extract response body"),
             CustomError::ConversionError => write!(f, "Unable to convert body to json"),
+            CustomError::InvalidSignature => {
+                write!(f, "Slack request signature could not be verified")
+            }
+            CustomError::DatabaseError => write!(f, "Unable to read or write message history"),
+            CustomError::SlackApiError {
+                code,
+                needed,
+                provided,
+            } => {
+                write!(f, "Slack API call failed with error: {}", code)?;
+                if let Some(needed) = needed {
+                    write!(f, ", needed: {}", needed)?;
+                }
+                if let Some(provided) = provided {
+                    write!(f, ", provided: {}", provided)?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
-impl ResponseError for CustomError {}
-
-///Helper for processing requests sent to slack
-async fn process_response(
-    response: reqwest::Result<reqwest::Response>,
-) -> actix_web::Result<web::Json<Value>, CustomError> {
-    //Check the response and return if it errors
-    if let Err(e) = response {
-        println!("{}", e);
-        return Err(CustomError::SlackResponseError);
-    }
-    //Get the body of the response
-    if let Ok(body) = response.unwrap().text().await {
-        //Try to convert it to json
-        let json: Value = serde_json::from_str(&body).map_err(|e| {
-            println!("Error is {}", e);
-            CustomError::ConversionError
-        })?;
-        //If all went well send the json as the response
-        Ok(web::Json(json))
-    } else {
-        Err(CustomError::BodyExtractionError)
+impl ResponseError for CustomError {
+    fn status_code(&self) -> http::StatusCode {
+        match self {
+            CustomError::InvalidSignature => http::StatusCode::UNAUTHORIZED,
+            _ => http::StatusCode::INTERNAL_SERVER_ERROR,
+        }
     }
 }
 
+/// Records a message (inbound or outbound) in the `messages` table.
+fn insert_message(pool: &DbPool, sender: &str, message_body: &str) -> Result<(), CustomError> {
+    let new_message = NewMessage {
+        sender,
+        body: message_body,
+        time_sent: chrono::Utc::now().naive_utc(),
+    };
+
+    let conn = pool.get().map_err(|_| CustomError::DatabaseError)?;
+    diesel::insert_into(schema::messages::table)
+        .values(&new_message)
+        .execute(&conn)
+        .map_err(|_| CustomError::DatabaseError)?;
+
+    Ok(())
+}
+
 /*****************************************HANDLERS***************************/
 
 ///Route that sends a message to the slack api
 #[post("/send-message")]
+#[tracing::instrument(skip(form, client, pool))]
 async fn send_message(
     form: web::Form<FormData>,
     client: web::Data<reqwest::Client>,
-) -> actix_web::Result<web::Json<Value>, CustomError> {
-    println!("form: {:?}", form.message);
+    pool: web::Data<DbPool>,
+) -> actix_web::Result<web::Json<PostMessageResponse>, CustomError> {
     let message = &form.message;
     let channel = &form.channel;
+    tracing::info!(channel = %channel, "sending message");
 
-    //Make a hashmap of the stuff we need to send in a form to slack
-    let mut body = HashMap::new();
-    body.insert("channel", channel);
-    body.insert("text", message);
+    let req = PostMessageRequest {
+        channel,
+        text: message,
+    };
 
-    let res = client
-        .post("https://slack.com/api/chat.postMessage")
-        .form(&body)
-        .send()
-        .await;
-    process_response(res).await
+    let res = slack::run_in_session("chat.postMessage", Some(channel), || {
+        client
+            .post("https://slack.com/api/chat.postMessage")
+            .form(&req)
+            .send()
+    })
+    .await;
+    let json = slack::process_response::<PostMessageResponse>(res).await?;
+
+    insert_message(&pool, "bot", message)?;
+
+    Ok(json)
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesQuery {
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+///Route that returns the stored message history so a frontend can render
+///conversation logs.
+#[get("/messages")]
+#[tracing::instrument(skip(pool))]
+async fn get_messages(
+    query: web::Query<MessagesQuery>,
+    pool: web::Data<DbPool>,
+) -> actix_web::Result<web::Json<Vec<models::Message>>, CustomError> {
+    use schema::messages::dsl::*;
+
+    let conn = pool.get().map_err(|_| CustomError::DatabaseError)?;
+    let results = messages
+        .order(id.desc())
+        .limit(query.limit.unwrap_or(50))
+        .offset(query.offset.unwrap_or(0))
+        .load::<models::Message>(&conn)
+        .map_err(|_| CustomError::DatabaseError)?;
+
+    Ok(web::Json(results))
 }
 
 #[get("/users")]
+#[tracing::instrument(skip(client))]
 async fn get_users(
+    query: web::Query<PaginationQuery>,
     client: web::Data<reqwest::Client>,
-) -> actix_web::Result<web::Json<Value>, CustomError> {
-    //Contact slack api
-    let res = client.get("https://slack.com/api/users.list").send().await;
-    //Check the response and return if it errors
-    if let Err(e) = res {
-        println!("{}", e);
-        return Err(CustomError::SlackResponseError);
-    }
-    process_response(res).await
+) -> actix_web::Result<web::Json<UsersListResponse>, CustomError> {
+    let response = slack::list_users(&client, &query).await?;
+    Ok(web::Json(response))
 }
 
 #[get("/conversations/{channel_id}")]
+#[tracing::instrument(skip(client))]
 async fn get_conversation_info(
     path: web::Path<String>,
     client: web::Data<reqwest::Client>,
+) -> actix_web::Result<web::Json<ConversationInfoResponse>, CustomError> {
+    let channel_id = path.into_inner();
+    let res = slack::run_in_session("conversations.info", Some(&channel_id), || {
+        client
+            .get(format!(
+                "https://slack.com/api/conversations.info?channel={}",
+                channel_id
+            ))
+            .send()
+    })
+    .await;
+    slack::process_response::<ConversationInfoResponse>(res).await
+}
+
+#[get("/conversations")]
+#[tracing::instrument(skip(client))]
+async fn get_conversations(
+    query: web::Query<PaginationQuery>,
+    client: web::Data<reqwest::Client>,
+) -> actix_web::Result<web::Json<ConversationsListResponse>, CustomError> {
+    let response = slack::list_conversations(&client, &query).await?;
+    Ok(web::Json(response))
+}
+
+///Route that receives Slack Events API callbacks (message events, the
+///url_verification handshake, etc). Every request is authenticated against
+///the app's signing secret before its body is parsed.
+#[post("/slack/events")]
+#[tracing::instrument(skip_all)]
+async fn slack_events(
+    req: HttpRequest,
+    body: web::Bytes,
+    config: web::Data<SlackConfig>,
+    pool: web::Data<DbPool>,
+    inbound: web::Data<Sender<InboundMessage>>,
 ) -> actix_web::Result<web::Json<Value>, CustomError> {
-    let res = client
-        .get(format!(
-            "https://slack.com/api/conversations.info?channel={}",
-            path.into_inner()
-        ))
-        .send()
-        .await;
-    process_response(res).await
+    let timestamp = req
+        .headers()
+        .get("X-Slack-Request-Timestamp")
+        .and_then(|v| v.to_str().ok())
+        .ok_or(CustomError::InvalidSignature)?;
+    let signature = req
+        .headers()
+        .get("X-Slack-Signature")
+        .and_then(|v| v.to_str().ok())
+        .ok_or(CustomError::InvalidSignature)?;
+
+    if !verify_slack_signature(&config.signing_secret, timestamp, signature, &body) {
+        return Err(CustomError::InvalidSignature);
+    }
+
+    let payload: SlackEventPayload = serde_json::from_slice(&body).map_err(|e| {
+        tracing::error!(error = %e, "failed to parse slack event payload");
+        CustomError::ConversionError
+    })?;
+
+    match payload {
+        SlackEventPayload::UrlVerification { challenge } => {
+            Ok(web::Json(serde_json::json!({ "challenge": challenge })))
+        }
+        SlackEventPayload::EventCallback { event } => {
+            tracing::info!(?event, "received slack event");
+
+            //Messages the bot itself posted (including our own auto-replies) come back
+            //through this same webhook; queuing those would trigger an unbounded
+            //self-reply loop, so skip anything Slack tags as bot-authored.
+            let is_from_bot = event.get("bot_id").is_some()
+                || event.get("subtype").and_then(Value::as_str) == Some("bot_message");
+            if is_from_bot {
+                return Ok(web::Json(serde_json::json!({ "ok": true })));
+            }
+
+            let sender = event.get("user").and_then(Value::as_str).unwrap_or("unknown");
+            if let Some(text) = event.get("text").and_then(Value::as_str) {
+                insert_message(&pool, sender, text)?;
+
+                if let Some(channel) = event.get("channel").and_then(Value::as_str) {
+                    let thread_ts = event
+                        .get("thread_ts")
+                        .or_else(|| event.get("ts"))
+                        .and_then(Value::as_str)
+                        .unwrap_or_default();
+
+                    let _ = inbound
+                        .send(InboundMessage {
+                            channel: channel.to_string(),
+                            thread_ts: thread_ts.to_string(),
+                            text: text.to_string(),
+                        })
+                        .await;
+                }
+            }
+            Ok(web::Json(serde_json::json!({ "ok": true })))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamReplyQuery {
+    prompt: String,
+}
+
+///Route that streams an LLM-generated reply to the client over
+///Server-Sent Events as it's generated, then posts the assembled message to
+///Slack and persists it once the stream completes.
+#[get("/stream-reply/{channel}")]
+#[tracing::instrument(skip(client, llm_config, pool))]
+async fn stream_reply(
+    path: web::Path<String>,
+    query: web::Query<StreamReplyQuery>,
+    client: web::Data<reqwest::Client>,
+    llm_config: web::Data<LlmConfig>,
+    pool: web::Data<DbPool>,
+) -> actix_web::HttpResponse {
+    let body = stream::relay(
+        client.as_ref().clone(),
+        llm_config.as_ref().clone(),
+        pool.as_ref().clone(),
+        path.into_inner(),
+        query.into_inner().prompt,
+    );
+
+    actix_web::HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .streaming(body)
 }
 
 #[actix_web::main] // or #[tokio::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
+    tracing_subscriber::fmt::init();
     //Set the headers for the client builder, will need to be overriden if they mismatch
     let mut headers = reqwest::header::HeaderMap::new();
     headers.insert(
@@ -132,13 +344,42 @@ async fn main() -> std::io::Result<()> {
     let client_builder = reqwest::ClientBuilder::new().default_headers(headers);
     let client = web::Data::new(client_builder.build().unwrap());
 
+    let slack_config = web::Data::new(SlackConfig {
+        signing_secret: dotenv::var("SLACK_SIGNING_SECRET").unwrap_or(String::new()),
+    });
+
+    let pool = web::Data::new(db::establish_pool());
+
+    //Spawn the background auto-responder, fed by inbound Slack messages over a channel
+    let (inbound_tx, inbound_rx) = tokio::sync::mpsc::channel::<InboundMessage>(100);
+    let inbound_tx = web::Data::new(inbound_tx);
+    let llm_config = web::Data::new(LlmConfig {
+        url: dotenv::var("LLM_API_URL").unwrap_or(String::new()),
+        api_key: dotenv::var("LLM_API_KEY").unwrap_or(String::new()),
+    });
+    tokio::spawn(worker::run(
+        inbound_rx,
+        client.as_ref().clone(),
+        llm_config.as_ref().clone(),
+        pool.as_ref().clone(),
+    ));
+
     HttpServer::new(move || {
         App::new()
             .wrap(setup_cors())
+            .wrap(tracing_actix_web::TracingLogger::default())
             .app_data(client.clone())
+            .app_data(slack_config.clone())
+            .app_data(pool.clone())
+            .app_data(inbound_tx.clone())
+            .app_data(llm_config.clone())
             .service(get_conversation_info)
+            .service(get_conversations)
             .service(get_users)
             .service(send_message)
+            .service(slack_events)
+            .service(get_messages)
+            .service(stream_reply)
     })
     .bind(("127.0.0.1", 8080))?
     .run()
@@ -1,7 +1,20 @@
-#[derive(Queryable)]
+use crate::schema::messages;
+use serde::Serialize;
+
+#[derive(Queryable, Serialize)]
 pub struct Message {
     pub id: i32,
     pub sender: String,
     pub body: String,
-    pub time_sent: chrono::NaiveDateTime
-}
\ No newline at end of file
+    pub time_sent: chrono::NaiveDateTime,
+}
+
+/// Row to insert for a message we sent to Slack or received from it;
+/// `id` is left out since it's generated by the database.
+#[derive(Insertable)]
+#[table_name = "messages"]
+pub struct NewMessage<'a> {
+    pub sender: &'a str,
+    pub body: &'a str,
+    pub time_sent: chrono::NaiveDateTime,
+}
@@ -0,0 +1,317 @@
+use actix_web::web;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::time::Instant;
+use tracing::Instrument;
+
+use crate::CustomError;
+
+/// Runs a Slack Web API call inside its own child span, so the request id
+/// from the enclosing `tracing_actix_web` span propagates into logs for
+/// every outbound hop, and records the method name, channel (when known),
+/// HTTP status, and latency once the call returns.
+pub async fn run_in_session<F, Fut>(
+    method: &'static str,
+    channel: Option<&str>,
+    f: F,
+) -> reqwest::Result<reqwest::Response>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = reqwest::Result<reqwest::Response>>,
+{
+    let span = tracing::info_span!(
+        "slack_api_call",
+        method,
+        channel = channel.unwrap_or("-"),
+        status = tracing::field::Empty,
+        latency_ms = tracing::field::Empty,
+    );
+
+    async move {
+        let start = Instant::now();
+        let result = f().await;
+        let latency_ms = start.elapsed().as_millis() as u64;
+        tracing::Span::current().record("latency_ms", latency_ms);
+
+        match &result {
+            Ok(response) => {
+                tracing::Span::current().record("status", response.status().as_u16());
+            }
+            Err(e) => tracing::error!(error = %e, "slack api call failed"),
+        }
+
+        result
+    }
+    .instrument(span)
+    .await
+}
+
+/// Implemented by every Slack Web API response payload so `process_response`
+/// can tell a transport success from a Slack logical failure
+/// (HTTP 200, body `{"ok": false, "error": "..."}`).
+pub trait SlackResponse {
+    fn ok(&self) -> bool;
+    fn error(&self) -> Option<&str>;
+    /// The scope/argument Slack expected, populated on errors like `missing_scope`.
+    fn needed(&self) -> Option<&str> {
+        None
+    }
+    /// The scope/argument Slack actually received, populated alongside `needed`.
+    fn provided(&self) -> Option<&str> {
+        None
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostMessageRequest<'a> {
+    pub channel: &'a str,
+    pub text: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostMessageResponse {
+    pub ok: bool,
+    pub channel: Option<String>,
+    pub ts: Option<String>,
+    pub error: Option<String>,
+    pub needed: Option<String>,
+    pub provided: Option<String>,
+}
+
+impl SlackResponse for PostMessageResponse {
+    fn ok(&self) -> bool {
+        self.ok
+    }
+    fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+    fn needed(&self) -> Option<&str> {
+        self.needed.as_deref()
+    }
+    fn provided(&self) -> Option<&str> {
+        self.provided.as_deref()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SlackUser {
+    pub id: String,
+    pub name: String,
+    pub real_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResponseMetadata {
+    pub next_cursor: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UsersListResponse {
+    pub ok: bool,
+    #[serde(default)]
+    pub members: Vec<SlackUser>,
+    pub response_metadata: Option<ResponseMetadata>,
+    pub error: Option<String>,
+    pub needed: Option<String>,
+    pub provided: Option<String>,
+}
+
+impl SlackResponse for UsersListResponse {
+    fn ok(&self) -> bool {
+        self.ok
+    }
+    fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+    fn needed(&self) -> Option<&str> {
+        self.needed.as_deref()
+    }
+    fn provided(&self) -> Option<&str> {
+        self.provided.as_deref()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SlackChannel {
+    pub id: String,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConversationInfoResponse {
+    pub ok: bool,
+    pub channel: Option<SlackChannel>,
+    pub error: Option<String>,
+    pub needed: Option<String>,
+    pub provided: Option<String>,
+}
+
+impl SlackResponse for ConversationInfoResponse {
+    fn ok(&self) -> bool {
+        self.ok
+    }
+    fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+    fn needed(&self) -> Option<&str> {
+        self.needed.as_deref()
+    }
+    fn provided(&self) -> Option<&str> {
+        self.provided.as_deref()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConversationsListResponse {
+    pub ok: bool,
+    #[serde(default)]
+    pub channels: Vec<SlackChannel>,
+    pub response_metadata: Option<ResponseMetadata>,
+    pub error: Option<String>,
+    pub needed: Option<String>,
+    pub provided: Option<String>,
+}
+
+impl SlackResponse for ConversationsListResponse {
+    fn ok(&self) -> bool {
+        self.ok
+    }
+    fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+    fn needed(&self) -> Option<&str> {
+        self.needed.as_deref()
+    }
+    fn provided(&self) -> Option<&str> {
+        self.provided.as_deref()
+    }
+}
+
+///Helper for processing requests sent to slack. Parses the body into a typed
+///response and turns a logical `{"ok": false}` into a `SlackApiError` so
+///callers get proper error propagation instead of a 200 full of nonsense.
+#[tracing::instrument(skip_all)]
+pub async fn process_response<T>(
+    response: reqwest::Result<reqwest::Response>,
+) -> actix_web::Result<web::Json<T>, CustomError>
+where
+    T: DeserializeOwned + SlackResponse,
+{
+    //Check the response and return if it errors
+    let response = response.map_err(|e| {
+        tracing::error!(error = %e, "transport error contacting slack");
+        CustomError::SlackResponseError
+    })?;
+
+    //Get the body of the response
+    let body = response
+        .text()
+        .await
+        .map_err(|_| CustomError::BodyExtractionError)?;
+
+    //Try to convert it to the expected shape
+    let parsed: T = serde_json::from_str(&body).map_err(|e| {
+        tracing::error!(error = %e, "failed to parse slack response body");
+        CustomError::ConversionError
+    })?;
+
+    if !parsed.ok() {
+        return Err(CustomError::SlackApiError {
+            code: parsed.error().unwrap_or("unknown_error").to_string(),
+            needed: parsed.needed().map(str::to_string),
+            provided: parsed.provided().map(str::to_string),
+        });
+    }
+
+    Ok(web::Json(parsed))
+}
+
+/// Cursor/limit query params shared by the paginated `*.list` endpoints.
+/// `all = true` tells the handler to follow `next_cursor` server-side until
+/// Slack stops returning one, instead of handing a single page back.
+#[derive(Debug, Deserialize)]
+pub struct PaginationQuery {
+    pub cursor: Option<String>,
+    pub limit: Option<u32>,
+    pub all: Option<bool>,
+}
+
+fn paginated_url(base: &str, cursor: Option<&str>, limit: Option<u32>) -> String {
+    let mut url = reqwest::Url::parse(base).expect("static Slack API url is valid");
+    {
+        let mut query = url.query_pairs_mut();
+        if let Some(cursor) = cursor.filter(|c| !c.is_empty()) {
+            query.append_pair("cursor", cursor);
+        }
+        if let Some(limit) = limit {
+            query.append_pair("limit", &limit.to_string());
+        }
+    }
+    url.into()
+}
+
+fn next_cursor(metadata: &Option<ResponseMetadata>) -> Option<String> {
+    metadata
+        .as_ref()
+        .map(|m| m.next_cursor.clone())
+        .filter(|c| !c.is_empty())
+}
+
+async fn fetch_users_page(
+    client: &reqwest::Client,
+    cursor: Option<&str>,
+    limit: Option<u32>,
+) -> Result<UsersListResponse, CustomError> {
+    let url = paginated_url("https://slack.com/api/users.list", cursor, limit);
+    let res = run_in_session("users.list", None, || client.get(url).send()).await;
+    Ok(process_response::<UsersListResponse>(res).await?.into_inner())
+}
+
+/// Fetches `users.list`, honoring `query.all` by following `next_cursor`
+/// server-side and concatenating every page's `members` into one response.
+pub async fn list_users(
+    client: &reqwest::Client,
+    query: &PaginationQuery,
+) -> Result<UsersListResponse, CustomError> {
+    let mut page = fetch_users_page(client, query.cursor.as_deref(), query.limit).await?;
+
+    if query.all.unwrap_or(false) {
+        while let Some(cursor) = next_cursor(&page.response_metadata) {
+            let mut next = fetch_users_page(client, Some(&cursor), query.limit).await?;
+            page.members.append(&mut next.members);
+            page.response_metadata = next.response_metadata;
+        }
+    }
+
+    Ok(page)
+}
+
+async fn fetch_conversations_page(
+    client: &reqwest::Client,
+    cursor: Option<&str>,
+    limit: Option<u32>,
+) -> Result<ConversationsListResponse, CustomError> {
+    let url = paginated_url("https://slack.com/api/conversations.list", cursor, limit);
+    let res = run_in_session("conversations.list", None, || client.get(url).send()).await;
+    Ok(process_response::<ConversationsListResponse>(res)
+        .await?
+        .into_inner())
+}
+
+/// Fetches `conversations.list`, honoring `query.all` the same way as
+/// [`list_users`].
+pub async fn list_conversations(
+    client: &reqwest::Client,
+    query: &PaginationQuery,
+) -> Result<ConversationsListResponse, CustomError> {
+    let mut page = fetch_conversations_page(client, query.cursor.as_deref(), query.limit).await?;
+
+    if query.all.unwrap_or(false) {
+        while let Some(cursor) = next_cursor(&page.response_metadata) {
+            let mut next = fetch_conversations_page(client, Some(&cursor), query.limit).await?;
+            page.channels.append(&mut next.channels);
+            page.response_metadata = next.response_metadata;
+        }
+    }
+
+    Ok(page)
+}
@@ -0,0 +1,108 @@
+use actix_web::web::Bytes;
+use async_stream::stream;
+use futures_util::{Stream, StreamExt};
+use serde::Deserialize;
+
+use crate::db::DbPool;
+use crate::insert_message;
+use crate::worker::{self, LlmConfig};
+
+/// One incremental chunk of an upstream streaming completion, matching the
+/// shape our LLM endpoint emits for each `data:` line.
+#[derive(Deserialize)]
+struct DeltaEvent {
+    delta: String,
+}
+
+/// Drives an upstream streaming LLM completion and relays each delta as an
+/// SSE frame to the client as it arrives. Once the upstream stream ends
+/// cleanly, the accumulated text is posted back to Slack and persisted, so
+/// the side effects happen exactly once the full reply is known. A
+/// mid-stream read error ends the stream without panicking, but the partial
+/// reply gathered so far is discarded rather than posted or persisted,
+/// since it isn't the complete answer.
+pub fn relay(
+    client: reqwest::Client,
+    llm_config: LlmConfig,
+    pool: DbPool,
+    channel: String,
+    prompt: String,
+) -> impl Stream<Item = Result<Bytes, actix_web::Error>> {
+    stream! {
+        tracing::info!(%channel, "opening llm stream");
+
+        let upstream = match worker::stream_reply(&client, &llm_config, &prompt).await {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::error!(error = %e, "failed to open llm stream");
+                return;
+            }
+        };
+
+        let mut bytes = upstream.bytes_stream();
+        // Raw bytes not yet known to be valid UTF-8; may end mid-character when a
+        // multi-byte codepoint straddles a chunk boundary.
+        let mut pending = Vec::new();
+        let mut buffer = String::new();
+        let mut full_text = String::new();
+        let mut stream_errored = false;
+
+        while let Some(chunk) = bytes.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    tracing::error!(error = %e, "error reading llm stream chunk");
+                    stream_errored = true;
+                    break;
+                }
+            };
+            pending.extend_from_slice(&chunk);
+
+            let valid_up_to = match std::str::from_utf8(&pending) {
+                Ok(text) => {
+                    buffer.push_str(text);
+                    pending.len()
+                }
+                Err(e) => {
+                    let valid_up_to = e.valid_up_to();
+                    // Safe: `valid_up_to` is guaranteed to be a valid UTF-8 boundary.
+                    buffer.push_str(std::str::from_utf8(&pending[..valid_up_to]).unwrap());
+                    valid_up_to
+                }
+            };
+            pending.drain(..valid_up_to);
+
+            while let Some(newline) = buffer.find('\n') {
+                let line = buffer[..newline].trim_end_matches('\r').to_string();
+                buffer.drain(..=newline);
+
+                let Some(data) = line.strip_prefix("data:") else {
+                    continue;
+                };
+                let data = data.trim();
+                if data.is_empty() || data == "[DONE]" {
+                    continue;
+                }
+
+                match serde_json::from_str::<DeltaEvent>(data) {
+                    Ok(event) => {
+                        full_text.push_str(&event.delta);
+                        yield Ok(Bytes::from(format!("data: {}\n\n", event.delta)));
+                    }
+                    Err(e) => tracing::error!(error = %e, "failed to parse llm stream event"),
+                }
+            }
+        }
+
+        if stream_errored {
+            tracing::error!(%channel, "llm stream ended with an error; discarding the partial reply instead of posting or persisting it");
+        } else if !full_text.is_empty() {
+            if let Err(e) = worker::post_reply(&client, &channel, "", &full_text).await {
+                tracing::error!(error = %e, %channel, "failed to post streamed reply");
+            }
+            if let Err(e) = insert_message(&pool, "bot", &full_text) {
+                tracing::error!(error = ?e, "failed to persist streamed reply");
+            }
+        }
+    }
+}
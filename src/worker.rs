@@ -0,0 +1,116 @@
+use reqwest::multipart::Form;
+use serde::Deserialize;
+use tokio::sync::mpsc::Receiver;
+
+use crate::db::DbPool;
+use crate::{insert_message, slack};
+
+/// Config for the external LLM endpoint used to generate automated replies.
+#[derive(Clone)]
+pub struct LlmConfig {
+    pub url: String,
+    pub api_key: String,
+}
+
+/// An inbound Slack message picked up by `slack_events`, queued for the
+/// auto-responder to pick up and reply to in the same thread.
+pub struct InboundMessage {
+    pub channel: String,
+    pub thread_ts: String,
+    pub text: String,
+}
+
+#[derive(Deserialize)]
+struct LlmResponse {
+    reply: String,
+}
+
+/// Consumes inbound messages and posts an LLM-generated reply back to Slack
+/// in the originating thread. Runs for the lifetime of the server; spawned
+/// once in `main` before the `HttpServer` starts accepting connections.
+pub async fn run(
+    mut inbound: Receiver<InboundMessage>,
+    client: reqwest::Client,
+    llm_config: LlmConfig,
+    pool: DbPool,
+) {
+    while let Some(message) = inbound.recv().await {
+        match generate_reply(&client, &llm_config, &message.text).await {
+            Ok(reply) => {
+                match post_reply(&client, &message.channel, &message.thread_ts, &reply).await {
+                    Ok(()) => {
+                        if let Err(e) = insert_message(&pool, "bot", &reply) {
+                            tracing::error!(error = ?e, channel = %message.channel, "failed to persist auto-reply");
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!(error = %e, channel = %message.channel, "failed to post auto-reply");
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::error!(error = %e, channel = %message.channel, "failed to generate auto-reply")
+            }
+        }
+    }
+}
+
+async fn generate_reply(
+    client: &reqwest::Client,
+    llm_config: &LlmConfig,
+    prompt: &str,
+) -> reqwest::Result<String> {
+    let response = client
+        .post(&llm_config.url)
+        .bearer_auth(&llm_config.api_key)
+        .json(&serde_json::json!({ "prompt": prompt }))
+        .send()
+        .await?
+        .json::<LlmResponse>()
+        .await?;
+
+    Ok(response.reply)
+}
+
+/// Posts a reply to Slack via `chat.postMessage`, threading it under
+/// `thread_ts` when one is given.
+pub async fn post_reply(
+    client: &reqwest::Client,
+    channel: &str,
+    thread_ts: &str,
+    text: &str,
+) -> reqwest::Result<()> {
+    let mut form = Form::new()
+        .text("text", text.to_string())
+        .text("channel", channel.to_string());
+    if !thread_ts.is_empty() {
+        form = form.text("thread_ts", thread_ts.to_string());
+    }
+
+    slack::run_in_session("chat.postMessage", Some(channel), || {
+        client
+            .post("https://slack.com/api/chat.postMessage")
+            .multipart(form)
+            .send()
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Opens a streaming completion request against the LLM endpoint. The
+/// caller reads the response body as a byte stream of upstream SSE `data:`
+/// chunks; the `stream` feature on `reqwest` is what makes `bytes_stream`
+/// available on the returned response.
+pub async fn stream_reply(
+    client: &reqwest::Client,
+    llm_config: &LlmConfig,
+    prompt: &str,
+) -> reqwest::Result<reqwest::Response> {
+    client
+        .post(&llm_config.url)
+        .bearer_auth(&llm_config.api_key)
+        .json(&serde_json::json!({ "prompt": prompt, "stream": true }))
+        .send()
+        .await
+}